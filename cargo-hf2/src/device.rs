@@ -0,0 +1,135 @@
+//! Enumerating and picking among multiple connected bootloader devices.
+//!
+//! The vendor scan used to silently grab the first matching device. This
+//! lets users with several boards attached see what's connected (`--list`)
+//! and pick one deterministically (`--device <index|serial>`).
+
+use colored::*;
+use hf2::utils::vendor_map;
+use hidapi::HidApi;
+use std::ffi::CString;
+use std::io::{self, BufRead, Write};
+
+/// A connected device that matches the requested vid/pid (or [`vendor_map`]),
+/// summarized for display and later re-opening by path.
+pub struct Candidate {
+    /// 1-based, stable for the lifetime of one enumeration; what `--device`
+    /// accepts besides a serial number.
+    pub index: usize,
+    pub manufacturer: String,
+    pub product: String,
+    pub serial: String,
+    pub vid: u16,
+    pub pid: u16,
+    path: CString,
+}
+
+impl std::fmt::Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {} - {} (serial: {}) {:04x}:{:04x}",
+            self.index, self.manufacturer, self.product, self.serial, self.vid, self.pid
+        )
+    }
+}
+
+/// Lists every currently connected device matching `vid_pid` (or, if `None`,
+/// anything in [`vendor_map`]).
+pub fn list_candidates(api: &HidApi, vid_pid: Option<(u16, u16)>) -> Vec<Candidate> {
+    let vendor = vendor_map();
+
+    api.device_list()
+        .filter(|device_info| match vid_pid {
+            Some((vid, pid)) => device_info.vendor_id() == vid && device_info.product_id() == pid,
+            None => vendor
+                .get(&device_info.vendor_id())
+                .map_or(false, |products| {
+                    products.contains(&device_info.product_id())
+                }),
+        })
+        .enumerate()
+        .map(|(i, device_info)| Candidate {
+            index: i + 1,
+            manufacturer: device_info
+                .manufacturer_string()
+                .unwrap_or("unknown")
+                .to_string(),
+            product: device_info
+                .product_string()
+                .unwrap_or("unknown")
+                .to_string(),
+            serial: device_info.serial_number().unwrap_or("unknown").to_string(),
+            vid: device_info.vendor_id(),
+            pid: device_info.product_id(),
+            path: device_info.path().to_owned(),
+        })
+        .collect()
+}
+
+/// Prints `candidates` in the `--list` format.
+pub fn print_list(candidates: &[Candidate]) {
+    if candidates.is_empty() {
+        println!("  no matching devices connected");
+        return;
+    }
+    for candidate in candidates {
+        println!("  {}", candidate);
+    }
+}
+
+/// Picks one of `candidates` by `selector` (an index or a serial number), or,
+/// if no selector was given, the only candidate, or an interactive pick when
+/// stdin is a TTY. Returns an error otherwise, after printing the candidates
+/// when there are several.
+pub fn select<'a>(
+    candidates: &'a [Candidate],
+    selector: Option<&str>,
+) -> Result<&'a Candidate, String> {
+    if let Some(selector) = selector {
+        return candidates
+            .iter()
+            .find(|c| c.index.to_string() == selector || c.serial == selector)
+            .ok_or_else(|| format!("no connected device matches --device {:?}", selector));
+    }
+
+    match candidates.len() {
+        0 => Err("no matching device connected".to_string()),
+        1 => Ok(&candidates[0]),
+        _ if atty::is(atty::Stream::Stdin) => prompt(candidates),
+        _ => {
+            print_list(candidates);
+            Err("multiple devices connected; pick one with --device <index|serial>".to_string())
+        }
+    }
+}
+
+fn prompt<'a>(candidates: &'a [Candidate]) -> Result<&'a Candidate, String> {
+    println!(
+        "   {} multiple devices connected, pick one:",
+        "Found".green().bold()
+    );
+    print_list(candidates);
+    print!("  > ");
+    io::stdout().flush().ok();
+
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    let index: usize = line
+        .trim()
+        .parse()
+        .map_err(|_| "not a number".to_string())?;
+
+    candidates
+        .iter()
+        .find(|c| c.index == index)
+        .ok_or_else(|| format!("no device with index {}", index))
+}
+
+/// Opens `candidate` by its HID path.
+pub fn open(api: &HidApi, candidate: &Candidate) -> hidapi::HidResult<hidapi::HidDevice> {
+    api.open_path(&candidate.path)
+}
@@ -0,0 +1,67 @@
+//! Wait for a device to appear in bootloader mode instead of failing
+//! immediately, modeled on the device monitor used by hardware security key
+//! libraries to catch a key being plugged in mid-poll.
+
+use crate::device::{self, Candidate};
+use colored::*;
+use hidapi::HidApi;
+use std::collections::HashSet;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often we re-scan the HID device list while waiting.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Polls for a device matching `vid_pid` (or [`crate::device::list_candidates`]'s
+/// default) to appear, returning all matching candidates as soon as any do,
+/// and giving up (`None`) once `timeout` elapses or if refreshing the HID
+/// device list fails.
+///
+/// Every scan re-reads the full HID device list and re-runs
+/// `list_candidates`'s vid/pid match, so this also notices a device that was
+/// already connected when we started polling but only just switched into
+/// bootloader mode. We additionally diff the set of connected HID paths
+/// against the previous scan and log newly-appeared ones, so a board
+/// enumerating is visible in `RUST_LOG=debug` even before it matches a
+/// vid/pid.
+pub fn wait_for_device(
+    api: &mut HidApi,
+    vid_pid: Option<(u16, u16)>,
+    timeout: Duration,
+) -> Option<Vec<Candidate>> {
+    println!(
+        "   {} for a device in bootloader mode (timeout {}s)...",
+        "Waiting".green().bold(),
+        timeout.as_secs()
+    );
+
+    let mut seen = connected_paths(api);
+
+    let start = Instant::now();
+    loop {
+        let candidates = device::list_candidates(api, vid_pid);
+        if !candidates.is_empty() {
+            return Some(candidates);
+        }
+
+        if start.elapsed() >= timeout {
+            return None;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+        api.refresh_devices().ok()?;
+
+        let now = connected_paths(api);
+        for path in now.difference(&seen) {
+            log::debug!("new HID device: {}", String::from_utf8_lossy(path));
+        }
+        seen = now;
+    }
+}
+
+/// The set of currently-connected HID device paths.
+fn connected_paths(api: &HidApi) -> HashSet<Vec<u8>> {
+    api.device_list()
+        .map(|d| d.path().to_bytes().to_vec())
+        .collect()
+}
@@ -1,12 +1,18 @@
 use cargo_metadata::Message;
 use colored::*;
-use hf2::utils::{elf_to_bin, flash_bin, vendor_map};
-use hidapi::{HidApi, HidDevice};
+use hf2::utils::{elf_to_bin, flash_bin, flash_bin_with_trace};
+use hidapi::HidApi;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use structopt::StructOpt;
 
+mod device;
+mod hotplug;
+mod monitor;
+mod trace;
+mod watch;
+
 fn main() {
     // Initialize the logging backend.
     pretty_env_logger::init();
@@ -19,14 +25,68 @@ fn main() {
 
     // todo, keep as iter. difficult because we want to filter map remove two items at once.
     // Remove our args as cargo build does not understand them.
-    let flags = ["--pid", "--vid"];
-    for flag in flags {
+    let value_flags = [
+        "--pid",
+        "--vid",
+        "--baud",
+        "--timeout",
+        "--device",
+        "--trace-file",
+    ];
+    for flag in value_flags {
         if let Some(index) = args.iter().position(|x| x == flag) {
             args.remove(index);
             args.remove(index);
         }
     }
+    let bool_flags = [
+        "--monitor",
+        "--wait",
+        "--list",
+        "--trace",
+        "--watch",
+        "--verify",
+    ];
+    for flag in bool_flags {
+        if let Some(index) = args.iter().position(|x| x == flag) {
+            args.remove(index);
+        }
+    }
+
+    let vid_pid = match (opt.vid, opt.pid) {
+        (Some(v), Some(p)) => Some((v, p)),
+        _ => None,
+    };
+
+    if opt.list {
+        let api = HidApi::new().expect("Couldn't find system usb");
+        device::print_list(&device::list_candidates(&api, vid_pid));
+        return;
+    }
+
+    if opt.watch {
+        let paths = watch::watch_paths(opt.manifest_path.as_deref());
+        let watcher = watch::Watcher::new(&paths).expect("failed to watch for source changes");
+        loop {
+            build_and_flash(&opt, &args, vid_pid);
+            println!(
+                "   {} for changes in {:?}...",
+                "Watching".green().bold(),
+                paths.iter().map(|(p, _)| p).collect::<Vec<_>>()
+            );
+            watcher.wait_for_change();
+        }
+    }
 
+    if !build_and_flash(&opt, &args, vid_pid) {
+        std::process::exit(1);
+    }
+}
+
+/// Runs one build+flash(+monitor) pipeline iteration. Returns `false` if the
+/// build fails (so `--watch` can skip cleanly instead of tearing down the
+/// whole process) or if `--verify` fails or finds a mismatch, `true` otherwise.
+fn build_and_flash(opt: &Opt, args: &[String], vid_pid: Option<(u16, u16)>) -> bool {
     // copy from probe-rs
     // https://github.com/probe-rs/probe-rs/blob/292818bc255ffe52ab20516e045728e774f2948f/probe-rs-cli-util/src/lib.rs#L112-L160
 
@@ -71,37 +131,37 @@ fn main() {
     }
 
     if !output.status.success() {
+        if opt.watch {
+            println!(
+                "   {} build failed, skipping flash",
+                "Warning".yellow().bold()
+            );
+            return false;
+        }
         exit_with_process_status(output.status);
     }
 
-    let api = HidApi::new().expect("Couldn't find system usb");
+    let mut api = HidApi::new().expect("Couldn't find system usb");
 
-    let d = if let (Some(v), Some(p)) = (opt.vid, opt.pid) {
-        api.open(v, p)
-            .expect("Are you sure device is plugged in and in bootloader mode?")
-    } else {
+    if vid_pid.is_none() {
         println!(
             "   {} for a connected device with known vid/pid pair.",
             "Searching".green().bold(),
         );
+    }
 
-        let mut device: Option<HidDevice> = None;
-
-        let vendor = vendor_map();
-
-        for device_info in api.device_list() {
-            if let Some(products) = vendor.get(&device_info.vendor_id()) {
-                if products.contains(&device_info.product_id()) {
-                    if let Ok(d) = device_info.open_device(&api) {
-                        device = Some(d);
-                        break;
-                    }
-                }
-            }
-        }
-        device.expect("Are you sure device is plugged in and in bootloader mode?")
+    let candidates = device::list_candidates(&api, vid_pid);
+    let candidates = if candidates.is_empty() && opt.wait {
+        hotplug::wait_for_device(&mut api, vid_pid, Duration::from_secs(opt.timeout))
+            .expect("timed out waiting for a device in bootloader mode")
+    } else {
+        candidates
     };
 
+    let candidate = device::select(&candidates, opt.device.as_deref())
+        .expect("Are you sure device is plugged in and in bootloader mode?");
+    let d = device::open(&api, candidate).expect("failed to open device");
+
     println!(
         "      {} {:?} {:?}",
         "Trying ".green().bold(),
@@ -117,15 +177,48 @@ fn main() {
 
     println!("    {} {:?}", "Flashing".green().bold(), path);
 
+    let symbolicator = if opt.monitor {
+        match monitor::Symbolicator::new(&path) {
+            Ok(symbolicator) => Some(symbolicator),
+            Err(e) => {
+                println!(
+                    "   {} couldn't load symbols from {:?}: {}",
+                    "Warning".yellow().bold(),
+                    path,
+                    e
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let ports_before_flash = monitor::current_ports();
+
     let (binary, address) = elf_to_bin(path).unwrap();
 
     // Start timer.
     let instant = Instant::now();
 
-    let bininfo = hf2::bin_info(&d).expect("bin_info failed");
+    let mut trace_sink: Option<Box<dyn hf2::trace::TraceSink>> = if opt.trace {
+        Some(match &opt.trace_file {
+            Some(path) => Box::new(trace::FileSink::new(path).expect("couldn't create trace file")),
+            None => Box::new(trace::StdoutSink),
+        })
+    } else {
+        None
+    };
+
+    let bininfo = match &mut trace_sink {
+        Some(sink) => hf2::bin_info_with_trace(&d, sink.as_mut()).expect("bin_info failed"),
+        None => hf2::bin_info(&d).expect("bin_info failed"),
+    };
     log::debug!("{:?}", bininfo);
 
-    flash_bin(&binary, address, &bininfo, &d).unwrap();
+    match &mut trace_sink {
+        Some(sink) => flash_bin_with_trace(&binary, address, &bininfo, &d, sink.as_mut()).unwrap(),
+        None => flash_bin(&binary, address, &bininfo, &d).unwrap(),
+    }
 
     // Stop timer.
     let elapsed = instant.elapsed();
@@ -134,6 +227,48 @@ fn main() {
         "Finished".green().bold(),
         elapsed.as_millis() as f32 / 1000.0
     );
+
+    if opt.verify {
+        println!(
+            "  {} written flash by reading it back",
+            "Verifying".green().bold()
+        );
+        match hf2::utils::verify_bin(&binary, address, &bininfo, &d) {
+            Ok(None) => println!("   {} read-back matches", "Verified".green().bold()),
+            Ok(Some(mismatch_address)) => {
+                eprintln!(
+                    "   {} read-back mismatch at 0x{:08x}",
+                    "Error".red().bold(),
+                    mismatch_address
+                );
+                return false;
+            }
+            Err(e) => {
+                eprintln!("   {} verify_bin failed: {:?}", "Error".red().bold(), e);
+                return false;
+            }
+        }
+    }
+
+    if opt.monitor {
+        println!(
+            "   {} for the application's serial port to enumerate...",
+            "Waiting".green().bold()
+        );
+        match monitor::wait_for_new_port(&ports_before_flash, Duration::from_secs(5)) {
+            Some(port) => {
+                if let Err(e) = monitor::run(&port, opt.baud, symbolicator) {
+                    eprintln!("{} monitor stopped: {}", "Error".red().bold(), e);
+                }
+            }
+            None => println!(
+                "   {} no new serial port showed up, nothing to monitor",
+                "Warning".yellow().bold()
+            ),
+        }
+    }
+
+    true
 }
 
 #[cfg(unix)]
@@ -183,4 +318,46 @@ struct Opt {
     pid: Option<u16>,
     #[structopt(name = "vid", long = "vid",  parse(try_from_str = parse_hex_16))]
     vid: Option<u16>,
+
+    /// After flashing, stream the application's serial output, symbolicating
+    /// any addresses found using the just-flashed ELF's debug info.
+    #[structopt(long)]
+    monitor: bool,
+    /// Baud rate to use with `--monitor`.
+    #[structopt(long, default_value = "115200")]
+    baud: u32,
+
+    /// Poll for a device in bootloader mode to appear instead of failing
+    /// immediately if none is connected yet.
+    #[structopt(long)]
+    wait: bool,
+    /// Seconds to poll for with `--wait` before giving up.
+    #[structopt(long, default_value = "30")]
+    timeout: u64,
+
+    /// List every connected device matching `--vid`/`--pid` (or any known
+    /// vendor/product pair) and exit without building or flashing.
+    #[structopt(long)]
+    list: bool,
+    /// Pick a specific device by its `--list` index or serial number, when
+    /// more than one is connected.
+    #[structopt(long)]
+    device: Option<String>,
+
+    /// Print every HF2 packet exchanged during `bin_info`/`flash_bin`.
+    #[structopt(long)]
+    trace: bool,
+    /// Write the `--trace` capture to a file instead of stdout.
+    #[structopt(long, parse(from_os_str))]
+    trace_file: Option<PathBuf>,
+
+    /// Keep running, rebuilding and re-flashing whenever a source file
+    /// changes.
+    #[structopt(long)]
+    watch: bool,
+
+    /// After flashing, read the written region back and compare it against
+    /// the binary byte-for-byte.
+    #[structopt(long)]
+    verify: bool,
 }
@@ -0,0 +1,164 @@
+//! Post-flash serial monitor with ELF-based backtrace symbolization.
+//!
+//! After a successful flash the bootloader resets the board into the user
+//! application, which typically enumerates a USB-CDC serial port and starts
+//! printing log/panic output. This module streams that output to the
+//! terminal and, since we still have the ELF that was just flashed, decodes
+//! any addresses that show up in it (e.g. a Cortex-M `HardFault` dump) into
+//! `function @ file:line`.
+
+use colored::*;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Resolves addresses inside a flashed ELF's executable range to
+/// `function @ file:line`, loading the debug info once up front.
+pub struct Symbolicator {
+    loader: addr2line::Loader,
+    text_start: u64,
+    text_end: u64,
+}
+
+impl Symbolicator {
+    /// Loads `path`'s symbol and debug tables and records the address range
+    /// covered by its `.text` section.
+    pub fn new(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let loader = addr2line::Loader::new(path).map_err(|e| format!("{}", e))?;
+
+        let bytes = std::fs::read(path)?;
+        let file = object::File::parse(&*bytes)?;
+        let text =
+            object::Object::section_by_name(&file, ".text").ok_or("ELF has no .text section")?;
+        let text_start = object::ObjectSection::address(&text);
+        let text_end = text_start + object::ObjectSection::size(&text);
+
+        Ok(Symbolicator {
+            loader,
+            text_start,
+            text_end,
+        })
+    }
+
+    /// Returns `Some("function @ file:line")` if `addr` falls inside the
+    /// executable range and resolves to debug info.
+    pub fn symbolicate(&self, addr: u64) -> Option<String> {
+        // Thumb addresses carry the mode bit in bit 0; mask it before lookup.
+        let addr = addr & !1;
+
+        if addr < self.text_start || addr >= self.text_end {
+            return None;
+        }
+
+        let mut frames = self.loader.find_frames(addr).ok()?;
+        let frame = frames.next().ok()??;
+
+        let function = frame
+            .function
+            .as_ref()
+            .and_then(|f| f.demangle().ok().map(|s| s.to_string()))
+            .unwrap_or_else(|| "???".to_string());
+
+        let location = frame
+            .location
+            .map(|loc| {
+                let file = loc.file.unwrap_or("??");
+                format!("{}:{}", file, loc.line.unwrap_or(0))
+            })
+            .unwrap_or_else(|| "??:0".to_string());
+
+        Some(format!("{} @ {}", function, location))
+    }
+}
+
+/// Compiled once: this is checked against every line in the monitor's
+/// hottest loop, so a panic/HardFault dump shouldn't pay for a regex
+/// recompile per line.
+static HEX_TOKEN_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"0x[0-9A-Fa-f]+|\b[0-9A-Fa-f]{8}\b").unwrap());
+
+/// Every hex token found in a line of serial output, e.g. `0x08004abc` or a
+/// bare `08004abc`, parsed as a number.
+fn hex_tokens(line: &str) -> Vec<u64> {
+    HEX_TOKEN_RE
+        .find_iter(line)
+        .filter_map(|m| {
+            let token = m.as_str().trim_start_matches("0x");
+            u64::from_str_radix(token, 16).ok()
+        })
+        .collect()
+}
+
+/// Waits for a new serial port to enumerate (the board resetting into its
+/// application) and returns its system path, giving up after `timeout`.
+pub fn wait_for_new_port(before: &[String], timeout: Duration) -> Option<String> {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if let Ok(ports) = serialport::available_ports() {
+            if let Some(p) = ports
+                .iter()
+                .map(|p| p.port_name.clone())
+                .find(|name| !before.contains(name))
+            {
+                return Some(p);
+            }
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    None
+}
+
+/// Lists the currently available serial port names.
+pub fn current_ports() -> Vec<String> {
+    serialport::available_ports()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| p.port_name)
+        .collect()
+}
+
+/// Opens `port` at `baud` and streams it to stdout, printing a symbolicated
+/// backtrace line for any in-range address found. Returns `Ok(())` when the
+/// port reaches EOF and an error on any read failure other than a timeout.
+pub fn run(
+    port: &str,
+    baud: u32,
+    symbolicator: Option<Symbolicator>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("  {} {} @ {} baud", "Monitoring".green().bold(), port, baud);
+
+    let serial = serialport::new(port, baud)
+        .timeout(Duration::from_millis(500))
+        .open()?;
+    let mut reader = BufReader::new(serial);
+
+    loop {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            // EOF: the port was closed or unplugged. Without this the loop
+            // would spin at full CPU re-reading 0 bytes forever.
+            Ok(0) => return Ok(()),
+            Ok(_) => {
+                let line = line.trim_end();
+                if line.is_empty() {
+                    continue;
+                }
+                println!("{}", line);
+
+                if let Some(symbolicator) = &symbolicator {
+                    for addr in hex_tokens(line) {
+                        if let Some(resolved) = symbolicator.symbolicate(addr) {
+                            println!("    {} {}", "->".blue().bold(), resolved);
+                        }
+                    }
+                }
+            }
+            // Timeouts are expected when the device is quiet; keep polling.
+            Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! HF2 packet trace / capture mode.
+//!
+//! `--trace` prints every HF2 HID packet exchanged during `bin_info` and
+//! `flash_bin`, similar in spirit to a usbmon capture but at the HF2 framing
+//! level: direction, command id, sequence/packet flags, and a hex dump of
+//! the payload. The packet type and the [`hf2::trace::TraceSink`] trait live
+//! in the `hf2` crate, since that's what calls back into a sink from its
+//! send/receive path; this module only owns the sink side.
+
+use hf2::trace::{Direction, Packet, TraceSink};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Prints packets to stdout as they happen.
+pub struct StdoutSink;
+
+impl TraceSink for StdoutSink {
+    fn trace(&mut self, packet: &Packet) {
+        println!("{}", format_packet(packet));
+    }
+}
+
+/// Writes packets to a file, for sharing in bug reports. An existing file is
+/// truncated when the sink is created.
+pub struct FileSink {
+    file: File,
+}
+
+impl FileSink {
+    pub fn new(path: &Path) -> io::Result<Self> {
+        Ok(FileSink {
+            file: File::create(path)?,
+        })
+    }
+}
+
+impl TraceSink for FileSink {
+    fn trace(&mut self, packet: &Packet) {
+        // A dropped write here would only lose a debugging aid, not
+        // correctness, so we don't propagate the error.
+        let _ = writeln!(self.file, "{}", format_packet(packet));
+    }
+}
+
+fn format_packet(packet: &Packet) -> String {
+    let direction = match packet.direction {
+        Direction::Tx => "TX",
+        Direction::Rx => "RX",
+    };
+    format!(
+        "{} cmd=0x{:08x} seq=0x{:04x} flags=0x{:02x}\n{}",
+        direction,
+        packet.command_id,
+        packet.sequence,
+        packet.flags,
+        hex_dump(&packet.payload)
+    )
+}
+
+/// A `hexdump -C`-style dump: 16 bytes per line, hex then ASCII.
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, chunk) in bytes.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+            .collect();
+        out.push_str(&format!(
+            "  {:04x}  {:<47}  |{}|\n",
+            i * 16,
+            hex.join(" "),
+            ascii
+        ));
+    }
+    out
+}
@@ -0,0 +1,70 @@
+//! Watch mode: rebuild and re-flash whenever the project's sources change,
+//! like cargo-plonk's notify-based loop.
+
+use notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebouncedEventKind};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+/// Events within this window are coalesced into a single settled batch, so a
+/// save-everything in an editor doesn't trigger a rebuild per file.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// The paths whose changes trigger a rebuild, both resolved via `cargo
+/// metadata`: the crate's `src/` tree (recursive), and the manifest
+/// directory itself, non-recursively so a `Cargo.toml`/`Cargo.lock` edit —
+/// e.g. a dep or feature bump — triggers a rebuild too without also
+/// re-watching `src/` or picking up `target/`'s own build output.
+pub fn watch_paths(manifest_path: Option<&Path>) -> Vec<(PathBuf, RecursiveMode)> {
+    let mut cmd = cargo_metadata::MetadataCommand::new();
+    if let Some(manifest_path) = manifest_path {
+        cmd.manifest_path(manifest_path);
+    }
+
+    let manifest_dir = match cmd.no_deps().exec() {
+        Ok(metadata) => metadata
+            .root_package()
+            .map(|p| p.manifest_path.parent().unwrap().into())
+            .unwrap_or(metadata.workspace_root),
+        Err(_) => PathBuf::from("."),
+    };
+    let manifest_dir = manifest_dir.into_std_path_buf();
+
+    vec![
+        (manifest_dir.join("src"), RecursiveMode::Recursive),
+        (manifest_dir, RecursiveMode::NonRecursive),
+    ]
+}
+
+/// Watches each of `paths` for changes, debounced.
+pub struct Watcher {
+    // Kept alive for as long as we want to keep receiving events.
+    _debouncer: notify_debouncer_mini::Debouncer<notify::RecommendedWatcher>,
+    events: Receiver<notify_debouncer_mini::DebounceEventResult>,
+}
+
+impl Watcher {
+    pub fn new(paths: &[(PathBuf, RecursiveMode)]) -> notify::Result<Self> {
+        let (tx, events) = channel();
+        let mut debouncer = new_debouncer(DEBOUNCE, tx)?;
+        for (path, mode) in paths {
+            debouncer.watcher().watch(path, *mode)?;
+        }
+
+        Ok(Watcher {
+            _debouncer: debouncer,
+            events,
+        })
+    }
+
+    /// Blocks until a settled batch of filesystem changes arrives.
+    pub fn wait_for_change(&self) {
+        while let Ok(result) = self.events.recv() {
+            let settled = matches!(result, Ok(events) if events.iter().any(|e| e.kind == DebouncedEventKind::Any));
+            if settled {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::command::{xmit, xmit_traced, Command};
+use crate::trace::TraceSink;
+use crate::Error;
+
+///Reset the device into user-space app. Empty tuple response.
+pub fn reset_into_app(d: &hidapi::HidDevice) -> Result<(), Error> {
+    xmit(Command::new(0x0003, 0, vec![]), d)
+}
+
+/// Like [`reset_into_app`], but reports the packet it sends to `sink`.
+pub(crate) fn reset_into_app_traced(
+    d: &hidapi::HidDevice,
+    sink: &mut dyn TraceSink,
+) -> Result<(), Error> {
+    xmit_traced(Command::new(0x0003, 0, vec![]), d, sink)
+}
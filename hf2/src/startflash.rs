@@ -0,0 +1,20 @@
+use crate::command::{rx, rx_traced, xmit, xmit_traced, Command};
+use crate::trace::TraceSink;
+use crate::Error;
+
+/// When issued in bootloader mode, it has no effect. In user-space mode it causes handover to bootloader. A BININFO command can be issued to verify that. Empty tuple response.
+pub fn start_flash(d: &hidapi::HidDevice) -> Result<(), Error> {
+    xmit(Command::new(0x0005, 0, vec![]), d)?;
+
+    rx(d).map(|_| ())
+}
+
+/// Like [`start_flash`], but reports every packet it sends/receives to `sink`.
+pub(crate) fn start_flash_traced(
+    d: &hidapi::HidDevice,
+    sink: &mut dyn TraceSink,
+) -> Result<(), Error> {
+    xmit_traced(Command::new(0x0005, 0, vec![]), d, sink)?;
+
+    rx_traced(d, sink, 0x0005).map(|_| ())
+}
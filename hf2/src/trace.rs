@@ -0,0 +1,31 @@
+//! Packet-level tracing of the HF2 send/receive path.
+//!
+//! `bin_info_with_trace`/`utils::flash_bin_with_trace` call back into a
+//! [`TraceSink`] for every HID packet they send or receive, so a caller (e.g.
+//! a CLI's `--trace` flag) can print or save a capture without this crate
+//! knowing anything about where that capture ends up.
+
+/// Which direction a packet travelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Host to device.
+    Tx,
+    /// Device to host.
+    Rx,
+}
+
+/// A single HF2 HID packet, decoded enough to be useful for debugging a
+/// flaky flash without a hardware USB analyzer.
+#[derive(Debug, Clone)]
+pub struct Packet {
+    pub direction: Direction,
+    pub command_id: u32,
+    pub sequence: u16,
+    pub flags: u8,
+    pub payload: Vec<u8>,
+}
+
+/// Something that wants to see every packet as it's sent/received.
+pub trait TraceSink {
+    fn trace(&mut self, packet: &Packet);
+}
@@ -0,0 +1,46 @@
+use crate::command::{rx, rx_traced, xmit, xmit_traced, Command};
+use crate::trace::TraceSink;
+use crate::Error;
+use scroll::Pwrite;
+
+///Write a single page of flash memory. Empty tuple response.
+pub fn write_flash_page(
+    d: &hidapi::HidDevice,
+    target_address: u32,
+    data: Vec<u8>,
+) -> Result<(), Error> {
+    xmit(
+        Command::new(0x0006, 0, page_buffer(target_address, &data)?),
+        d,
+    )?;
+
+    rx(d).map(|_| ())
+}
+
+/// Like [`write_flash_page`], but reports every packet it sends/receives to `sink`.
+pub(crate) fn write_flash_page_traced(
+    d: &hidapi::HidDevice,
+    target_address: u32,
+    data: Vec<u8>,
+    sink: &mut dyn TraceSink,
+) -> Result<(), Error> {
+    xmit_traced(
+        Command::new(0x0006, 0, page_buffer(target_address, &data)?),
+        d,
+        sink,
+    )?;
+
+    rx_traced(d, sink, 0x0006).map(|_| ())
+}
+
+fn page_buffer(target_address: u32, data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut buffer = vec![0_u8; data.len() + 4];
+    let mut offset = 0;
+
+    buffer.gwrite_with(target_address, &mut offset, scroll::LE)?;
+    for i in data {
+        buffer.gwrite_with(i, &mut offset, scroll::LE)?;
+    }
+
+    Ok(buffer)
+}